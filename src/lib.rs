@@ -3,11 +3,33 @@ use itertools::Itertools;
 use nanorand::{Rng, WyRand};
 use std::collections::HashMap;
 
-/// Builds a Markov chain of characters from a list of names.
-/// `order` determines how many characters to use as context (e.g., 2 = bi-gram).
+/// A Markov chain with back-off: `chains[n - 1]` holds the order-`n`
+/// transition table, so the full structure spans every order from 1 up to
+/// the chain's built order.
+///
+/// Each context maps to its observed successors *with a frequency count*
+/// rather than one `char` slot per occurrence, so a character seen 500
+/// times costs one `(char, u32)` entry instead of 500 `char`s. The counts
+/// also leave room for future probability smoothing (e.g. add-one/Laplace)
+/// by seeding unseen successors with a small count.
+pub type Chain = Vec<HashMap<String, Vec<(char, u32)>>>;
+
+/// Builds a Markov chain of characters from a list of names, together with
+/// every lower-order chain down to order 1.
+///
+/// `order` determines how many characters to use as context (e.g., 2 =
+/// bi-gram) for the highest-order table. Keeping every lower order around
+/// lets generation back off to a shorter context when a full-order one
+/// hasn't been observed, instead of terminating the name early.
 #[must_use]
-pub fn build_chain(names: &[&str], order: usize) -> HashMap<String, Vec<char>> {
-    let mut chain: HashMap<String, Vec<_>> = HashMap::new();
+pub fn build_chain(names: &[&str], order: usize) -> Chain {
+    (1..=order)
+        .map(|current_order| build_chain_of_order(names, current_order))
+        .collect()
+}
+
+fn build_chain_of_order(names: &[&str], order: usize) -> HashMap<String, Vec<(char, u32)>> {
+    let mut chain: HashMap<String, Vec<(char, u32)>> = HashMap::new();
 
     for &name in names {
         let padded = format!("{}{}", "^".repeat(order), name.to_lowercase());
@@ -16,38 +38,83 @@ pub fn build_chain(names: &[&str], order: usize) -> HashMap<String, Vec<char>> {
         for window in chars.windows(order + 1) {
             let (key_slice, next) = window.split_at(order);
             let key = key_slice.iter().collect();
-            chain.entry(key).or_default().push(next[0]);
+            let next_char = next[0];
+
+            let successors = chain.entry(key).or_default();
+            match successors.iter_mut().find(|(c, _)| *c == next_char) {
+                Some((_, count)) => *count += 1,
+                None => successors.push((next_char, 1)),
+            }
         }
     }
 
     chain
 }
 
+/// Looks up the weighted successors for `context`, backing off from the
+/// chain's full order down to order 1 until a non-empty entry is found.
+///
+/// `context` must hold at least as many characters as the chain's order;
+/// only the rightmost `n` characters are used when trying order `n`.
+fn lookup_with_backoff<'a>(chain: &'a Chain, context: &str) -> Option<&'a Vec<(char, u32)>> {
+    chain.iter().enumerate().rev().find_map(|(index, table)| {
+        let n = index + 1;
+        let start = context.char_indices().rev().nth(n - 1).map_or(0, |(i, _)| i);
+        table.get(&context[start..]).filter(|next| !next.is_empty())
+    })
+}
+
+/// Draws one successor out of `weighted_next`, picking a random value in
+/// `0..total_count` and walking the cumulative weights to find it.
+fn sample_weighted(weighted_next: &[(char, u32)], rng: &mut WyRand) -> char {
+    let total: u32 = weighted_next.iter().map(|(_, count)| count).sum();
+    let pick = rng.generate_range(..total);
+
+    let mut cumulative = 0;
+    weighted_next
+        .iter()
+        .find_map(|&(c, count)| {
+            cumulative += count;
+            (pick < cumulative).then_some(c)
+        })
+        .unwrap_or(weighted_next[weighted_next.len() - 1].0)
+}
+
 /// Generates a new name using the Markov chain.
+///
+/// Internally seeds a single `WyRand` from entropy and delegates to
+/// [`generate_name_seeded`].
 #[must_use]
-pub fn generate_name<S: std::hash::BuildHasher>(
-    chain: &HashMap<String, Vec<char>, S>,
-    order: usize,
-    max_len: usize,
-) -> String {
+pub fn generate_name(chain: &Chain, max_len: usize) -> String {
+    generate_name_seeded(chain, max_len, WyRand::new().generate::<u64>())
+}
+
+/// Generates a new name using the Markov chain, driven by a single `WyRand`
+/// constructed from `seed`.
+///
+/// Because the RNG is seeded once and threaded through every character, the
+/// same `seed` (and `chain`/`max_len`) always produces the same name, which
+/// makes generated names reproducible and snapshot-testable.
+///
+/// The chain's order is taken from `chain.len()` (the order it was built
+/// with in [`build_chain`]); when the full-order context hasn't been
+/// observed, generation backs off to shorter and shorter contexts (down to
+/// order 1) before conceding.
+#[must_use]
+pub fn generate_name_seeded(chain: &Chain, max_len: usize, seed: u64) -> String {
+    let order = chain.len();
+    let mut rng = WyRand::new_seed(seed);
     let mut current: String = "^".repeat(order);
     let mut result = String::new();
 
     for _ in 0..max_len {
-        if let Some(next_chars) = chain.get(&current) {
-            if let Some(next_char) = next_chars
-                // if next_chars is empty it will generate 0 => get = None
-                .get(WyRand::new().generate_range(..next_chars.len()))
-                .copied()
-            {
-                if next_char == '^' || next_char == '\0' {
-                    break;
-                }
-                result.push(next_char);
-                current = format!("{}{}", &current[1..], next_char);
-            } else {
+        if let Some(weighted_next) = lookup_with_backoff(chain, &current) {
+            let next_char = sample_weighted(weighted_next, &mut rng);
+            if next_char == '^' || next_char == '\0' {
                 break;
             }
+            result.push(next_char);
+            current = format!("{}{}", &current[1..], next_char);
         } else {
             // Word generation graceful end => word completely generated
             result.push('^');
@@ -56,18 +123,179 @@ pub fn generate_name<S: std::hash::BuildHasher>(
     }
     ensure_complete_name(result)
 }
+
 #[must_use]
-/// Capitalize all the substrings contained in a string.
+/// Assembles a compound name by picking one entry from each word bank in
+/// `parts` and joining the picks with `sep`.
 ///
-/// `sep` is the separator used to recognize substrings
+/// This is an alternative to the character-level Markov generators above:
+/// instead of training on a corpus, it draws from curated word lists (e.g.
+/// an adjective bank and a noun bank) to produce always-pronounceable
+/// multi-word names such as `"Golden Sap Flicker"`.
+///
+/// `rng` is taken by reference so callers can share a single seeded
+/// `WyRand` across several `generate_compound` calls and get fully
+/// reproducible output.
 /// ```Rust
-/// let x = capitalize_each_substring("hi who are you?", " ") // Could also use None
-/// println!(x) // "Hi Who Are You?"
-/// let y = capitalize_each_substring("hi,who", ",")
-/// println!(y) // "Hi,Who"
+/// let mut rng = WyRand::new_seed(42);
+/// let name = generate_compound(&[&["golden", "barkskin"], &["sap", "listener"]], " ", &mut rng);
 /// ```
-pub fn capitalize_each_substring(s: &str, sep: &str) -> String {
-    s.split(sep).map(capitalize_string).join(sep)
+pub fn generate_compound(parts: &[&[&str]], sep: &str, rng: &mut WyRand) -> String {
+    parts
+        .iter()
+        // if a bank is empty, generate_range(..0) yields 0 => get = None
+        .filter_map(|bank| bank.get(rng.generate_range(..bank.len())).copied())
+        .join(sep)
+}
+
+/// Default set of minor words left lowercase by [`title_case`] (unless they
+/// open or close the title).
+pub const DEFAULT_MINOR_WORDS: &[&str] = &[
+    "a", "an", "the", "of", "in", "on", "to", "and", "but", "or", "for", "with", "by", "as",
+];
+
+/// Generates a name using the Markov chain, seeded for reproducibility, and
+/// title-cases the result with [`title_case`].
+///
+/// This is the generation API's opt-in for title casing: callers who want
+/// the raw lowercase output can keep using [`generate_name_seeded`], while
+/// callers who want proper title casing (minor words lowercase unless they
+/// open or close the name) can use this instead.
+#[must_use]
+pub fn generate_name_seeded_titled(chain: &Chain, max_len: usize, seed: u64) -> String {
+    title_case(&generate_name_seeded(chain, max_len, seed))
+}
+
+#[must_use]
+/// Title-case a string using [`DEFAULT_MINOR_WORDS`] as the minor-word
+/// exception list. See [`title_case_with_exceptions`] for custom lists.
+pub fn title_case(s: &str) -> String {
+    title_case_with_exceptions(s, DEFAULT_MINOR_WORDS)
+}
+
+#[must_use]
+/// Title-case a string, lowercasing any word found in `minor_words` unless
+/// it is the first or last word of the string.
+///
+/// `minor_words` is matched case-insensitively, so callers can pass their
+/// own exception list to tune which articles, conjunctions, and short
+/// prepositions stay lowercase for a given naming theme.
+/// ```Rust
+/// let x = title_case_with_exceptions("watcher of falling", &["of"]);
+/// println!(x) // "Watcher of Falling"
+/// ```
+pub fn title_case_with_exceptions(s: &str, minor_words: &[&str]) -> String {
+    let words: Vec<&str> = s.split(' ').collect();
+    let last_index = words.len().saturating_sub(1);
+
+    words
+        .iter()
+        .enumerate()
+        .map(|(index, &word)| {
+            let is_minor_word = minor_words
+                .iter()
+                .any(|minor| minor.eq_ignore_ascii_case(word));
+            if index != 0 && index != last_index && is_minor_word {
+                word.to_lowercase()
+            } else {
+                capitalize_string(word)
+            }
+        })
+        .join(" ")
+}
+
+/// Output-case conventions that [`format_name`] can render a name into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Case {
+    /// `Watcher of Falling` (minor words lowercase, see [`title_case`])
+    Title,
+    /// `watcher of falling`
+    Lower,
+    /// `WATCHER OF FALLING`
+    Upper,
+    /// `watcherOfFalling`
+    Camel,
+    /// `WatcherOfFalling`
+    Pascal,
+    /// `watcher_of_falling`
+    Snake,
+    /// `WATCHER_OF_FALLING`
+    ScreamingSnake,
+    /// `watcher-of-falling`
+    Kebab,
+    /// `Watcher-Of-Falling`
+    Train,
+    /// `watcheroffalling`
+    Flat,
+}
+
+#[must_use]
+/// Segments `name` into words and re-joins them in the given [`Case`].
+///
+/// Words are split on spaces, hyphens, underscores, and camel-case
+/// boundaries (a lowercase letter followed by an uppercase one), so names
+/// coming from any of the other casing functions in this crate can be
+/// re-cased freely.
+pub fn format_name(name: &str, case: Case) -> String {
+    let words = split_into_words(name);
+
+    match case {
+        Case::Title => title_case(&words.join(" ")),
+        Case::Lower => words.join(" ").to_lowercase(),
+        Case::Upper => words.join(" ").to_uppercase(),
+        Case::Camel => join_camel(&words, false),
+        Case::Pascal => join_camel(&words, true),
+        Case::Snake => words.iter().map(|w| w.to_lowercase()).join("_"),
+        Case::ScreamingSnake => words.iter().map(|w| w.to_uppercase()).join("_"),
+        Case::Kebab => words.iter().map(|w| w.to_lowercase()).join("-"),
+        Case::Train => words
+            .iter()
+            .map(|w| capitalize_string(&w.to_lowercase()))
+            .join("-"),
+        Case::Flat => words.iter().map(|w| w.to_lowercase()).join(""),
+    }
+}
+
+fn join_camel(words: &[String], pascal_case: bool) -> String {
+    words
+        .iter()
+        .enumerate()
+        .map(|(index, word)| {
+            if index == 0 && !pascal_case {
+                word.to_lowercase()
+            } else {
+                capitalize_string(&word.to_lowercase())
+            }
+        })
+        .join("")
+}
+
+/// Splits `s` into words on spaces, hyphens, underscores, and camel-case
+/// boundaries (a lowercase letter immediately followed by an uppercase one).
+fn split_into_words(s: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_was_lower = false;
+
+    for c in s.chars() {
+        if c == ' ' || c == '-' || c == '_' {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            prev_was_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_was_lower && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_was_lower = c.is_lowercase();
+        current.push(c);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    words
 }
 
 #[must_use]
@@ -95,52 +323,51 @@ mod tests {
     use rstest::rstest;
 
     #[rstest]
-    #[case("hi who are you", " ", String::from("Hi Who Are You"))]
-    #[case("hi;who;are;you", ";", String::from("Hi;Who;Are;You"))]
-    #[case("hi who;are you", ";", String::from("Hi who;Are you"))]
-    fn capitalize_substring_correct_separator(
+    #[case("Kaeryel Alenar", Case::Title, String::from("Kaeryel Alenar"))]
+    #[case("Kaeryel Alenar", Case::Lower, String::from("kaeryel alenar"))]
+    #[case("Kaeryel Alenar", Case::Upper, String::from("KAERYEL ALENAR"))]
+    #[case("Kaeryel Alenar", Case::Camel, String::from("kaeryelAlenar"))]
+    #[case("Kaeryel Alenar", Case::Pascal, String::from("KaeryelAlenar"))]
+    #[case("Kaeryel Alenar", Case::Snake, String::from("kaeryel_alenar"))]
+    #[case("Kaeryel Alenar", Case::ScreamingSnake, String::from("KAERYEL_ALENAR"))]
+    #[case("Kaeryel Alenar", Case::Kebab, String::from("kaeryel-alenar"))]
+    #[case("Kaeryel Alenar", Case::Train, String::from("Kaeryel-Alenar"))]
+    #[case("Kaeryel Alenar", Case::Flat, String::from("kaeryelalenar"))]
+    #[case("KaeryelAlenar", Case::Snake, String::from("kaeryel_alenar"))]
+    #[case("kaeryel-alenar", Case::Pascal, String::from("KaeryelAlenar"))]
+    fn format_name_renders_each_case(
         #[case] input_str: &str,
-        #[case] sep: &str,
+        #[case] case: Case,
         #[case] expected: String,
     ) {
-        let result = capitalize_each_substring(input_str, sep);
+        let result = format_name(input_str, case);
         assert_eq!(expected, result);
     }
 
-    #[rstest]
-    #[case("hi Who are you", " ", String::from("Hi Who Are You"))]
-    #[case("hi;who;Are;you", ";", String::from("Hi;Who;Are;You"))]
-    fn capitalize_substring_correct_separator_some_substring_already_capitalized(
-        #[case] input_str: &str,
-        #[case] sep: &str,
-        #[case] expected: String,
-    ) {
-        let result = capitalize_each_substring(input_str, sep);
-        assert_eq!(expected, result);
-    }
+    #[test]
+    fn generate_compound_picks_one_entry_per_bank_and_joins_with_sep() {
+        let adjectives: &[&str] = &["golden", "barkskin"];
+        let nouns: &[&str] = &["sap", "listener"];
+        let mut rng = WyRand::new_seed(42);
 
-    #[rstest]
-    #[case("hi who are you", ";", String::from("Hi who are you"))]
-    #[case("hi;who;are;you", " ", String::from("Hi;who;are;you"))]
-    fn capitalize_substring_wrong_separator(
-        #[case] input_str: &str,
-        #[case] sep: &str,
-        #[case] expected: String,
-    ) {
-        let result = capitalize_each_substring(input_str, sep);
-        assert_eq!(expected, result);
+        let name = generate_compound(&[adjectives, nouns], " ", &mut rng);
+
+        let (adjective, noun) = name.split_once(' ').expect("two words joined by sep");
+        assert!(adjectives.contains(&adjective));
+        assert!(nouns.contains(&noun));
     }
 
-    #[rstest]
-    #[case("hi Who are you", ";", String::from("Hi who are you"))]
-    #[case("hi;who;Are;you", " ", String::from("Hi;who;are;you"))]
-    fn capitalize_substring_wrong_separator_some_substring_already_capitalized(
-        #[case] input_str: &str,
-        #[case] sep: &str,
-        #[case] expected: String,
-    ) {
-        let result = capitalize_each_substring(input_str, sep);
-        assert_eq!(expected, result);
+    #[test]
+    fn generate_compound_is_deterministic_for_a_given_seed() {
+        let parts: &[&[&str]] = &[&["golden", "barkskin"], &["sap", "listener"]];
+
+        let mut first_rng = WyRand::new_seed(7);
+        let first = generate_compound(parts, " ", &mut first_rng);
+
+        let mut second_rng = WyRand::new_seed(7);
+        let second = generate_compound(parts, " ", &mut second_rng);
+
+        assert_eq!(first, second);
     }
 
     #[rstest]
@@ -208,4 +435,79 @@ mod tests {
         let result = ensure_complete_name(input_name);
         assert_eq!(result, expected);
     }
+
+    #[rstest]
+    #[case(42)]
+    #[case(1337)]
+    #[case(0)]
+    fn generate_name_seeded_is_deterministic(#[case] seed: u64) {
+        let chain = build_chain(&["kaeryel", "alenar", "ysildea", "marco", "gianni"], 2);
+        let first = generate_name_seeded(&chain, 12, seed);
+        let second = generate_name_seeded(&chain, 12, seed);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn generate_name_seeded_does_not_panic_on_a_multi_order_chain() {
+        // Regression test: the chain's order used to be passed in separately
+        // from the chain itself, so a caller-supplied order smaller than
+        // `chain.len()` underflowed the back-off lookup. Deriving the order
+        // from `chain.len()` makes that mismatch impossible.
+        let chain = build_chain(&["marco", "gianni"], 3);
+        let name = generate_name_seeded(&chain, 10, 1);
+        assert!(!name.is_empty());
+    }
+
+    #[test]
+    fn build_chain_stores_every_order_up_to_the_requested_one() {
+        let chain = build_chain(&["marco"], 3);
+        assert_eq!(chain.len(), 3);
+    }
+
+    #[test]
+    fn build_chain_records_one_weighted_entry_per_distinct_successor() {
+        // "aa" repeated twice feeds the order-1 context "a" -> 'a' twice,
+        // which should collapse into a single (char, count) entry rather
+        // than two separate `char` slots.
+        let chain = build_chain(&["aa", "aa"], 1);
+        let successors = chain[0].get("a").expect("context `a` was observed");
+        assert_eq!(successors, &vec![('a', 2)]);
+    }
+
+    #[rstest]
+    #[case("watcher of falling", String::from("Watcher of Falling"))]
+    #[case("nestle in wintern", String::from("Nestle in Wintern"))]
+    #[case("of mice and men", String::from("Of Mice and Men"))]
+    #[case("gianni", String::from("Gianni"))]
+    #[case("", String::from(""))]
+    fn title_case_lowercases_minor_words_except_first_and_last(
+        #[case] input_str: &str,
+        #[case] expected: String,
+    ) {
+        let result = title_case(input_str);
+        assert_eq!(expected, result);
+    }
+
+    #[rstest]
+    #[case("barkskin und listener", &["und"], String::from("Barkskin und Listener"))]
+    #[case("the barkskin of old", &["the"], String::from("The Barkskin Of Old"))]
+    fn title_case_with_exceptions_uses_the_given_minor_word_list(
+        #[case] input_str: &str,
+        #[case] minor_words: &[&str],
+        #[case] expected: String,
+    ) {
+        let result = title_case_with_exceptions(input_str, minor_words);
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn generate_name_backs_off_instead_of_truncating_on_an_unseen_high_order_context() {
+        // "marcoma" ends on the order-3 context "oma", which has no
+        // successor; only the order-2 context "ma" does (-> 'r'), so
+        // generation should back off and keep going instead of stopping at
+        // "marcoma", cycling through "marco" until `max_len` is reached.
+        let chain = build_chain(&["marcoma"], 3);
+        let name = generate_name_seeded(&chain, 20, 7);
+        assert_eq!(name, "marcomarcomarcomarco");
+    }
 }